@@ -0,0 +1,90 @@
+use cargo_lambda_remote::aws_sdk_lambda::{model::AliasRoutingConfiguration, Client as LambdaClient};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::collections::HashMap;
+
+/// Returns the version an alias currently points to, or `None` if the alias
+/// doesn't exist yet.
+pub(crate) async fn get_version(
+    lambda_client: &LambdaClient,
+    name: &str,
+    alias: &str,
+) -> Result<Option<String>> {
+    let result = lambda_client
+        .get_alias()
+        .function_name(name)
+        .name(alias)
+        .send()
+        .await;
+
+    match result {
+        Ok(output) => Ok(output.function_version),
+        Err(err)
+            if err
+                .as_service_error()
+                .is_some_and(|e| e.is_resource_not_found_exception()) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err)
+            .into_diagnostic()
+            .wrap_err(format!("failed to look up the `{alias}` alias")),
+    }
+}
+
+/// Points `alias` entirely at `version`, clearing any weighted routing.
+pub(crate) async fn point_to(
+    lambda_client: &LambdaClient,
+    name: &str,
+    alias: &str,
+    version: &str,
+) -> Result<()> {
+    set_routing(lambda_client, name, alias, version, HashMap::new()).await
+}
+
+/// Points `alias` at `primary_version`, shifting the given `weights` of
+/// traffic to the other versions in the map. Creates the alias if it
+/// doesn't exist yet.
+pub(crate) async fn set_routing(
+    lambda_client: &LambdaClient,
+    name: &str,
+    alias: &str,
+    primary_version: &str,
+    weights: HashMap<String, f64>,
+) -> Result<()> {
+    let routing_config = AliasRoutingConfiguration::builder()
+        .set_additional_version_weights(Some(weights))
+        .build();
+
+    let update = lambda_client
+        .update_alias()
+        .function_name(name)
+        .name(alias)
+        .function_version(primary_version)
+        .routing_config(routing_config.clone())
+        .send()
+        .await;
+
+    match update {
+        Ok(_) => Ok(()),
+        Err(err)
+            if err
+                .as_service_error()
+                .is_some_and(|e| e.is_resource_not_found_exception()) =>
+        {
+            lambda_client
+                .create_alias()
+                .function_name(name)
+                .name(alias)
+                .function_version(primary_version)
+                .routing_config(routing_config)
+                .send()
+                .await
+                .into_diagnostic()
+                .wrap_err(format!("failed to create the `{alias}` alias"))?;
+            Ok(())
+        }
+        Err(err) => Err(err)
+            .into_diagnostic()
+            .wrap_err(format!("failed to update the `{alias}` alias")),
+    }
+}
@@ -0,0 +1,202 @@
+use crate::alias;
+use cargo_lambda_interactive::progress::Progress;
+use cargo_lambda_remote::aws_sdk_lambda::{model::LogType, types::Blob, Client as LambdaClient};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde_json::Value;
+
+pub(crate) struct CanaryCheck {
+    payload: Vec<u8>,
+    expect: Option<(String, Value)>,
+}
+
+impl CanaryCheck {
+    /// Parses the `--canary-payload`/`--canary-expect` flags. The payload is
+    /// read from a file if one exists at that path, otherwise it's treated
+    /// as an inline JSON literal.
+    pub(crate) fn load(payload_arg: &str, expect_arg: &Option<String>) -> Result<Self> {
+        let payload = std::fs::read(payload_arg).unwrap_or_else(|_| payload_arg.as_bytes().to_vec());
+
+        let expect = expect_arg
+            .as_ref()
+            .map(|raw| {
+                let (pointer, value) = raw.split_once('=').ok_or_else(|| {
+                    miette::miette!("invalid --canary-expect `{raw}`, expected `<json-pointer>=<value>`")
+                })?;
+                let pointer = if pointer.starts_with('/') {
+                    pointer.to_string()
+                } else {
+                    format!("/{pointer}")
+                };
+                let value: Value =
+                    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+                Ok::<_, miette::Report>((pointer, value))
+            })
+            .transpose()?;
+
+        Ok(CanaryCheck { payload, expect })
+    }
+}
+
+/// Invokes the newly published `version` of `name` with the canary payload
+/// and, if it looks healthy, promotes `alias` to it. If it doesn't, `alias`
+/// is rolled back to whatever it pointed at before this deploy (when it
+/// already existed) and an error carrying the tail of the invocation logs
+/// is returned.
+pub(crate) async fn deploy_with_canary(
+    lambda_client: &LambdaClient,
+    name: &str,
+    alias_name: &str,
+    new_version: &str,
+    check: &CanaryCheck,
+    progress: &Progress,
+) -> Result<()> {
+    let previous_version = alias::get_version(lambda_client, name, alias_name).await?;
+
+    match invoke_canary(lambda_client, name, new_version, check, progress).await {
+        Ok(()) => {
+            alias::point_to(lambda_client, name, alias_name, new_version).await?;
+            Ok(())
+        }
+        Err(err) => {
+            if let Some(previous_version) = previous_version {
+                progress.set_message(format!("canary check failed, rolling back `{alias_name}`"));
+                alias::point_to(lambda_client, name, alias_name, &previous_version).await?;
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Invokes `version` with the canary payload and checks that it looks
+/// healthy, without touching any alias. Used both for a plain canary deploy
+/// and as the health check between steps of a gradual rollout.
+pub(crate) async fn invoke_canary(
+    lambda_client: &LambdaClient,
+    name: &str,
+    version: &str,
+    check: &CanaryCheck,
+    progress: &Progress,
+) -> Result<()> {
+    progress.set_message(format!("invoking canary version {version}"));
+
+    let qualified_name = format!("{name}:{version}");
+    let invoke = lambda_client
+        .invoke()
+        .function_name(qualified_name)
+        .payload(Blob::new(check.payload.clone()))
+        .log_type(LogType::Tail)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to invoke the canary version")?;
+
+    let log_tail = invoke
+        .log_result
+        .as_deref()
+        .map(decode_log_tail)
+        .transpose()?;
+
+    if let Some(function_error) = &invoke.function_error {
+        return Err(canary_failure(
+            format!("the canary invocation failed with a function error: {function_error}"),
+            log_tail,
+        ));
+    }
+
+    if invoke.status_code != 200 {
+        return Err(canary_failure(
+            format!(
+                "the canary invocation returned status code {}",
+                invoke.status_code
+            ),
+            log_tail,
+        ));
+    }
+
+    if let Some((pointer, expected)) = &check.expect {
+        let response: Value = invoke
+            .payload
+            .map(|blob| serde_json::from_slice(blob.as_ref()))
+            .transpose()
+            .into_diagnostic()
+            .wrap_err("failed to parse the canary response payload as JSON")?
+            .unwrap_or(Value::Null);
+
+        let actual = response.pointer(pointer);
+        if actual != Some(expected) {
+            return Err(canary_failure(
+                format!("expected `{pointer}` to equal `{expected}`, but found `{actual:?}`"),
+                log_tail,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn canary_failure(message: String, log_tail: Option<String>) -> miette::Report {
+    match log_tail {
+        Some(logs) => miette::miette!("{message}\n\ncanary logs:\n{logs}"),
+        None => miette::miette!("{message}"),
+    }
+}
+
+fn decode_log_tail(encoded: &str) -> Result<String> {
+    use base64::Engine;
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .into_diagnostic()
+        .wrap_err("failed to decode the canary log tail")?;
+    String::from_utf8(bytes)
+        .into_diagnostic()
+        .wrap_err("canary log tail wasn't valid utf-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_inline_json_payload_without_expect() {
+        let check = CanaryCheck::load(r#"{"ping": true}"#, &None).unwrap();
+        assert_eq!(check.payload, br#"{"ping": true}"#);
+        assert!(check.expect.is_none());
+    }
+
+    #[test]
+    fn parses_expect_with_leading_slash() {
+        let check = CanaryCheck::load("{}", &Some("/status=\"ok\"".to_string())).unwrap();
+        let (pointer, value) = check.expect.unwrap();
+        assert_eq!(pointer, "/status");
+        assert_eq!(value, Value::String("ok".to_string()));
+    }
+
+    #[test]
+    fn parses_expect_without_leading_slash() {
+        let check = CanaryCheck::load("{}", &Some("status=\"ok\"".to_string())).unwrap();
+        let (pointer, value) = check.expect.unwrap();
+        assert_eq!(pointer, "/status");
+        assert_eq!(value, Value::String("ok".to_string()));
+    }
+
+    #[test]
+    fn parses_expect_with_non_string_json_value() {
+        let check = CanaryCheck::load("{}", &Some("/code=200".to_string())).unwrap();
+        let (pointer, value) = check.expect.unwrap();
+        assert_eq!(pointer, "/code");
+        assert_eq!(value, Value::from(200));
+    }
+
+    #[test]
+    fn falls_back_to_string_value_when_not_valid_json() {
+        let check = CanaryCheck::load("{}", &Some("/status=ok".to_string())).unwrap();
+        let (_, value) = check.expect.unwrap();
+        assert_eq!(value, Value::String("ok".to_string()));
+    }
+
+    #[test]
+    fn rejects_expect_without_equals() {
+        assert!(CanaryCheck::load("{}", &Some("no-equals-sign".to_string())).is_err());
+    }
+}
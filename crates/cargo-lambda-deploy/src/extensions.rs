@@ -0,0 +1,94 @@
+use crate::{extract_tags, CodeSource};
+use cargo_lambda_remote::aws_sdk_lambda::{
+    model::{Architecture, LayerVersionContentInput, Runtime},
+    Client as LambdaClient,
+};
+use cargo_lambda_interactive::progress::Progress;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Serialize;
+use std::{collections::HashMap, path::Path};
+
+#[derive(Serialize)]
+pub struct DeployOutput {
+    pub name: String,
+    pub arn: String,
+    pub version: String,
+}
+
+impl std::fmt::Display for DeployOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "🔧 extension {} published as version {} ({})",
+            self.name, self.version, self.arn
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn deploy(
+    name: &str,
+    _manifest_path: &Path,
+    sdk_config: &aws_types::SdkConfig,
+    code: CodeSource,
+    architecture: Architecture,
+    compatible_runtimes: Vec<Runtime>,
+    tags: &Option<Vec<String>>,
+    progress: &Progress,
+) -> Result<DeployOutput> {
+    let lambda_client = LambdaClient::new(sdk_config);
+
+    let content = match code {
+        CodeSource::Zip(bytes) => LayerVersionContentInput::builder()
+            .zip_file(bytes.into())
+            .build(),
+        CodeSource::S3 { bucket, key } => LayerVersionContentInput::builder()
+            .s3_bucket(bucket)
+            .s3_key(key)
+            .build(),
+    };
+
+    progress.set_message("publishing layer version");
+    let output = lambda_client
+        .publish_layer_version()
+        .layer_name(name)
+        .content(content)
+        .set_compatible_runtimes(Some(compatible_runtimes))
+        .compatible_architectures(architecture)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to publish the extension layer")?;
+
+    let arn = output
+        .layer_version_arn
+        .ok_or_else(|| miette::miette!("missing layer version arn in the response"))?;
+    let version = output.version.to_string();
+
+    if let Some(tags) = tags {
+        let tags = extract_tags(tags);
+        tag_layer(&lambda_client, &arn, tags).await?;
+    }
+
+    Ok(DeployOutput {
+        name: name.into(),
+        arn,
+        version,
+    })
+}
+
+async fn tag_layer(
+    lambda_client: &LambdaClient,
+    resource_arn: &str,
+    tags: HashMap<String, String>,
+) -> Result<()> {
+    lambda_client
+        .tag_resource()
+        .resource(resource_arn)
+        .set_tags(Some(tags))
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to tag the extension layer")?;
+    Ok(())
+}
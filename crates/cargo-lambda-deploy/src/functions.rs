@@ -0,0 +1,309 @@
+use crate::{
+    canary::{deploy_with_canary, CanaryCheck},
+    extract_tags, roles,
+    rollout::{self, DeploymentStrategy},
+    CodeSource,
+};
+use cargo_lambda_remote::{
+    aws_sdk_iam::Client as IamClient,
+    aws_sdk_lambda::{
+        model::{Architecture, Environment, FunctionCode, TracingConfig, TracingMode},
+        Client as LambdaClient,
+    },
+    RemoteConfig,
+};
+use cargo_lambda_interactive::progress::Progress;
+use clap::Args;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Serialize;
+use std::{collections::HashMap, path::Path};
+
+/// Alias promoted by a canary check when `--alias` isn't set.
+const LIVE_ALIAS: &str = "live";
+
+#[derive(Args, Clone, Debug)]
+pub struct FunctionDeployConfig {
+    /// Memory allocated for the function, in megabytes
+    #[arg(long)]
+    memory: Option<i32>,
+
+    /// Maximum execution time for the function, in seconds
+    #[arg(long)]
+    timeout: Option<i32>,
+
+    /// IAM role to attach to the function, created automatically if it doesn't exist
+    #[arg(long)]
+    iam_role: Option<String>,
+
+    /// Option to add one or more environment variables, allows multiple repetitions (--env-var FOO=BAR)
+    #[arg(long)]
+    env_var: Option<Vec<String>>,
+
+    /// Enable AWS X-Ray tracing for the function
+    #[arg(long)]
+    tracing: bool,
+
+    /// Create a public URL for this function
+    #[arg(long)]
+    pub(crate) enable_function_url: bool,
+
+    /// Remove the public URL for this function, if any
+    #[arg(long)]
+    pub(crate) disable_function_url: bool,
+
+    /// Path to a JSON file, or an inline JSON literal, to invoke the new version with
+    /// as a smoke test before it's promoted to the alias (--alias, default `live`)
+    #[arg(long)]
+    canary_payload: Option<String>,
+
+    /// JSON pointer and expected value that the canary response must match, in the form
+    /// `<json-pointer>=<value>` (--canary-expect /status=ok)
+    #[arg(long, requires = "canary_payload")]
+    canary_expect: Option<String>,
+
+    /// Name of the alias to manage for gradual rollouts and canary promotion
+    #[arg(long)]
+    alias: Option<String>,
+
+    /// Traffic shifting strategy to use when promoting the new version on --alias:
+    /// `all-at-once`, `canary:<pct>/<interval-secs>`, or `linear:<step-pct>/<interval-secs>`
+    #[arg(long, requires = "alias", default_value = "all-at-once")]
+    deployment_strategy: DeploymentStrategy,
+}
+
+#[derive(Serialize)]
+pub struct DeployOutput {
+    pub name: String,
+    pub arn: String,
+    pub version: String,
+    pub function_url: Option<String>,
+}
+
+impl std::fmt::Display for DeployOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "🔧 function {} published as version {} ({})",
+            self.name, self.version, self.arn
+        )?;
+        if let Some(url) = &self.function_url {
+            write!(f, "\n🔗 {url}")?;
+        }
+        Ok(())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn deploy(
+    name: &str,
+    binary_name: &str,
+    _manifest_path: &Path,
+    function_config: &FunctionDeployConfig,
+    remote_config: &RemoteConfig,
+    sdk_config: &aws_types::SdkConfig,
+    code: CodeSource,
+    architecture: Architecture,
+    tags: &Option<Vec<String>>,
+    progress: &Progress,
+) -> Result<DeployOutput> {
+    let lambda_client = LambdaClient::new(sdk_config);
+    let iam_client = IamClient::new(sdk_config);
+
+    let role_name = function_config
+        .iam_role
+        .clone()
+        .unwrap_or_else(|| format!("cargo-lambda-role-{name}"));
+    let role_arn = roles::get_or_create_function_role(&iam_client, &role_name).await?;
+
+    let code = match code {
+        CodeSource::Zip(bytes) => FunctionCode::builder().zip_file(bytes.into()).build(),
+        CodeSource::S3 { bucket, key } => FunctionCode::builder()
+            .s3_bucket(bucket)
+            .s3_key(key)
+            .build(),
+    };
+
+    let environment = function_config.env_var.as_ref().map(|vars| {
+        Environment::builder()
+            .set_variables(Some(extract_tags(vars)))
+            .build()
+    });
+
+    let tracing_config = function_config.tracing.then(|| {
+        TracingConfig::builder()
+            .mode(TracingMode::Active)
+            .build()
+    });
+
+    progress.set_message("creating or updating function code");
+    let function_exists = match lambda_client.get_function().function_name(name).send().await {
+        Ok(_) => true,
+        Err(err)
+            if err
+                .as_service_error()
+                .is_some_and(|e| e.is_resource_not_found_exception()) =>
+        {
+            false
+        }
+        Err(err) => {
+            return Err(err)
+                .into_diagnostic()
+                .wrap_err(format!("failed to check whether function `{name}` already exists"))
+        }
+    };
+
+    let arn = if function_exists {
+        let arn = lambda_client
+            .update_function_code()
+            .function_name(name)
+            .set_zip_file(code.zip_file().cloned())
+            .set_s3_bucket(code.s3_bucket().map(String::from))
+            .set_s3_key(code.s3_key().map(String::from))
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to update the function code")?
+            .function_arn;
+
+        progress.set_message("updating function configuration");
+        lambda_client
+            .update_function_configuration()
+            .function_name(name)
+            .set_memory_size(function_config.memory)
+            .set_timeout(function_config.timeout)
+            .set_environment(environment.clone())
+            .set_tracing_config(tracing_config.clone())
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to update the function configuration")?;
+
+        arn
+    } else {
+        lambda_client
+            .create_function()
+            .function_name(name)
+            .handler(binary_name)
+            .role(&role_arn)
+            .code(code)
+            .runtime(remote_config.runtime())
+            .architectures(architecture)
+            .set_memory_size(function_config.memory)
+            .set_timeout(function_config.timeout)
+            .set_environment(environment)
+            .set_tracing_config(tracing_config)
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to create the function")?
+            .function_arn
+    }
+    .ok_or_else(|| miette::miette!("missing function arn in the response"))?;
+
+    progress.set_message("publishing new version");
+    let publish = lambda_client
+        .publish_version()
+        .function_name(name)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to publish a new version")?;
+    let version = publish
+        .version
+        .ok_or_else(|| miette::miette!("missing version in the publish response"))?;
+
+    if let Some(tags) = tags {
+        let tags = extract_tags(tags);
+        tag_function(&lambda_client, &arn, tags).await?;
+    }
+
+    let check = function_config
+        .canary_payload
+        .as_ref()
+        .map(|payload| CanaryCheck::load(payload, &function_config.canary_expect))
+        .transpose()?;
+
+    if let Some(alias_name) = &function_config.alias {
+        rollout::roll_out(
+            &lambda_client,
+            name,
+            alias_name,
+            &version,
+            &function_config.deployment_strategy,
+            check.as_ref(),
+            progress,
+        )
+        .await?;
+    } else if let Some(check) = &check {
+        deploy_with_canary(&lambda_client, name, LIVE_ALIAS, &version, check, progress).await?;
+    }
+
+    let function_url = if function_config.enable_function_url {
+        Some(create_function_url(&lambda_client, name).await?)
+    } else {
+        if function_config.disable_function_url {
+            delete_function_url(&lambda_client, name).await?;
+        }
+        None
+    };
+
+    Ok(DeployOutput {
+        name: name.into(),
+        arn,
+        version,
+        function_url,
+    })
+}
+
+async fn tag_function(
+    lambda_client: &LambdaClient,
+    resource_arn: &str,
+    tags: HashMap<String, String>,
+) -> Result<()> {
+    lambda_client
+        .tag_resource()
+        .resource(resource_arn)
+        .set_tags(Some(tags))
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to tag the function")?;
+    Ok(())
+}
+
+async fn create_function_url(lambda_client: &LambdaClient, name: &str) -> Result<String> {
+    let output = lambda_client
+        .create_function_url_config()
+        .function_name(name)
+        .auth_type(cargo_lambda_remote::aws_sdk_lambda::model::FunctionUrlAuthType::None)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to create the function url")?;
+
+    output
+        .function_url
+        .ok_or_else(|| miette::miette!("missing function url in the response"))
+}
+
+async fn delete_function_url(lambda_client: &LambdaClient, name: &str) -> Result<()> {
+    let result = lambda_client
+        .delete_function_url_config()
+        .function_name(name)
+        .send()
+        .await;
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(err)
+            if err
+                .as_service_error()
+                .is_some_and(|e| e.is_resource_not_found_exception()) =>
+        {
+            Ok(())
+        }
+        Err(err) => Err(err)
+            .into_diagnostic()
+            .wrap_err("failed to delete the function url"),
+    }
+}
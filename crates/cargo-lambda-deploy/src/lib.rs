@@ -4,18 +4,32 @@ use cargo_lambda_interactive::progress::Progress;
 use cargo_lambda_metadata::cargo::main_binary;
 use cargo_lambda_remote::{
     aws_sdk_lambda::model::{Architecture, Runtime},
+    aws_sdk_s3::Client as S3Client,
     RemoteConfig,
 };
 use clap::{Args, ValueHint};
 use miette::{IntoDiagnostic, Result, WrapErr};
 use serde::Serialize;
 use serde_json::ser::to_string_pretty;
-use std::{collections::HashMap, fs::read, path::PathBuf, time::Duration};
+use std::{collections::HashMap, fs::read, path::PathBuf, time::Duration, time::SystemTime};
 use strum_macros::{Display, EnumString};
 
+mod alias;
+mod canary;
 mod extensions;
 mod functions;
 mod roles;
+mod rollout;
+mod s3;
+mod state_machines;
+
+/// Where the zip archive to deploy comes from: embedded directly in the
+/// Lambda/Extension API call, or already uploaded to S3 so the call can
+/// reference it by bucket and key instead.
+pub(crate) enum CodeSource {
+    Zip(Vec<u8>),
+    S3 { bucket: String, key: String },
+}
 
 #[derive(Clone, Debug, Display, EnumString)]
 #[strum(ascii_case_insensitive)]
@@ -29,6 +43,7 @@ enum OutputFormat {
 enum DeployResult {
     Extension(extensions::DeployOutput),
     Function(functions::DeployOutput),
+    StateMachine(state_machines::DeployOutput),
 }
 
 impl std::fmt::Display for DeployResult {
@@ -36,6 +51,17 @@ impl std::fmt::Display for DeployResult {
         match self {
             DeployResult::Extension(o) => o.fmt(f),
             DeployResult::Function(o) => o.fmt(f),
+            DeployResult::StateMachine(o) => o.fmt(f),
+        }
+    }
+}
+
+impl DeployResult {
+    fn name_and_arn(&self) -> (&str, &str) {
+        match self {
+            DeployResult::Extension(o) => (&o.name, &o.arn),
+            DeployResult::Function(o) => (&o.name, &o.arn),
+            DeployResult::StateMachine(o) => (&o.name, &o.arn),
         }
     }
 }
@@ -108,6 +134,11 @@ pub struct Deploy {
     #[arg(short, long)]
     include: Option<Vec<PathBuf>>,
 
+    /// Path to an Amazon States Language definition (JSON or YAML) to deploy alongside the function,
+    /// with `${cargo_lambda:NAME}` placeholders resolved to the ARN of the function just deployed
+    #[arg(long, value_hint = ValueHint::FilePath, conflicts_with = "extension")]
+    state_machine: Option<PathBuf>,
+
     /// Name of the function or extension to deploy
     #[arg(value_name = "NAME")]
     name: Option<String>,
@@ -144,9 +175,23 @@ impl Deploy {
             .map(|runtime| Runtime::from(runtime.as_str()))
             .collect::<Vec<_>>();
 
-        let binary_data = read(&archive.path)
-            .into_diagnostic()
-            .wrap_err("failed to read binary archive")?;
+        let code = match &self.s3_bucket {
+            Some(bucket) => {
+                let s3_client = S3Client::new(&sdk_config);
+                let key = self.s3_key(&name)?;
+                s3::upload_archive(&s3_client, bucket, &key, &archive.path, &progress).await?;
+                CodeSource::S3 {
+                    bucket: bucket.clone(),
+                    key,
+                }
+            }
+            None => {
+                let binary_data = read(&archive.path)
+                    .into_diagnostic()
+                    .wrap_err("failed to read binary archive")?;
+                CodeSource::Zip(binary_data)
+            }
+        };
 
         let mut tags = self.tags.clone();
         if tags.is_none() {
@@ -158,10 +203,9 @@ impl Deploy {
                 &name,
                 &self.manifest_path,
                 &sdk_config,
-                binary_data,
+                code,
                 architecture,
                 compatible_runtimes,
-                &self.s3_bucket,
                 &tags,
                 &progress,
             )
@@ -175,31 +219,72 @@ impl Deploy {
                 &self.function_config,
                 &self.remote_config,
                 &sdk_config,
-                &self.s3_bucket,
-                &tags,
-                binary_data,
+                code,
                 architecture,
+                &tags,
                 &progress,
             )
             .await
         };
 
+        let output = match result {
+            Ok(output) => output,
+            Err(err) => {
+                progress.finish_and_clear();
+                return Err(err);
+            }
+        };
         progress.finish_and_clear();
-        let output = result?;
+        self.print_result(&output)?;
+
+        if let Some(state_machine) = &self.state_machine {
+            let (deployed_name, deployed_arn) = output.name_and_arn();
+            let sm_result = state_machines::deploy(
+                state_machine,
+                deployed_name,
+                deployed_arn,
+                &sdk_config,
+                &tags,
+                &progress,
+            )
+            .await;
+            let sm_output = match sm_result {
+                Ok(sm_output) => sm_output,
+                Err(err) => {
+                    progress.finish_and_clear();
+                    return Err(err);
+                }
+            };
+            progress.finish_and_clear();
+            self.print_result(&DeployResult::StateMachine(sm_output))?;
+        }
+
+        Ok(())
+    }
 
+    fn print_result(&self, output: &DeployResult) -> Result<()> {
         match &self.output_format {
             OutputFormat::Text => println!("{output}"),
             OutputFormat::Json => {
-                let text = to_string_pretty(&output)
+                let text = to_string_pretty(output)
                     .into_diagnostic()
                     .wrap_err("failed to serialize output into json")?;
                 println!("{text}")
             }
         }
-
         Ok(())
     }
 
+    /// Builds the S3 key under which the archive for `name` will be uploaded.
+    fn s3_key(&self, name: &str) -> Result<String> {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .into_diagnostic()
+            .wrap_err("failed to compute the upload timestamp")?
+            .as_secs();
+        Ok(format!("{name}/{timestamp}.zip"))
+    }
+
     fn load_archive(&self) -> Result<(String, BinaryArchive)> {
         let arc = match &self.binary_path {
             Some(bp) if bp.is_dir() => return Err(miette::miette!("invalid file {:?}", bp)),
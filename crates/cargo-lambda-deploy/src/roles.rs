@@ -0,0 +1,112 @@
+use cargo_lambda_remote::aws_sdk_iam::Client as IamClient;
+use miette::{IntoDiagnostic, Result, WrapErr};
+
+const LAMBDA_BASIC_EXECUTION_POLICY_ARN: &str =
+    "arn:aws:iam::aws:policy/service-role/AWSLambdaBasicExecutionRole";
+
+const STATES_EXECUTION_POLICY_ARN: &str =
+    "arn:aws:iam::aws:policy/service-role/AWSLambdaRole";
+
+/// Finds the role with the given name, or creates a new one that trusts
+/// `service_principal` and has `policy_arn` attached if it doesn't exist yet.
+pub(crate) async fn get_or_create_role(
+    iam_client: &IamClient,
+    name: &str,
+    service_principal: &str,
+    policy_arn: &str,
+) -> Result<String> {
+    match iam_client.get_role().role_name(name).send().await {
+        Ok(output) => {
+            let role = output
+                .role
+                .ok_or_else(|| miette::miette!("missing role information for `{}`", name))?;
+            let arn = role
+                .arn
+                .ok_or_else(|| miette::miette!("missing role arn for `{}`", name))?;
+            Ok(arn)
+        }
+        Err(err)
+            if err
+                .as_service_error()
+                .is_some_and(|e| e.is_no_such_entity_exception()) =>
+        {
+            create_role(iam_client, name, service_principal, policy_arn).await
+        }
+        Err(err) => Err(err)
+            .into_diagnostic()
+            .wrap_err(format!("failed to fetch role `{}`", name)),
+    }
+}
+
+/// Finds or creates the role used by deployed functions, trusting the
+/// Lambda service and carrying the basic execution policy.
+pub(crate) async fn get_or_create_function_role(iam_client: &IamClient, name: &str) -> Result<String> {
+    get_or_create_role(
+        iam_client,
+        name,
+        "lambda.amazonaws.com",
+        LAMBDA_BASIC_EXECUTION_POLICY_ARN,
+    )
+    .await
+}
+
+/// Finds or creates the role used by deployed state machines, trusting the
+/// Step Functions service and carrying a policy that lets it invoke Lambda
+/// functions.
+pub(crate) async fn get_or_create_state_machine_role(
+    iam_client: &IamClient,
+    name: &str,
+) -> Result<String> {
+    get_or_create_role(
+        iam_client,
+        name,
+        "states.amazonaws.com",
+        STATES_EXECUTION_POLICY_ARN,
+    )
+    .await
+}
+
+async fn create_role(
+    iam_client: &IamClient,
+    name: &str,
+    service_principal: &str,
+    policy_arn: &str,
+) -> Result<String> {
+    let trust_policy = format!(
+        r#"{{
+  "Version": "2012-10-17",
+  "Statement": [
+    {{
+      "Effect": "Allow",
+      "Principal": {{ "Service": "{service_principal}" }},
+      "Action": "sts:AssumeRole"
+    }}
+  ]
+}}"#
+    );
+
+    let output = iam_client
+        .create_role()
+        .role_name(name)
+        .assume_role_policy_document(trust_policy)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err(format!("failed to create role `{}`", name))?;
+
+    let arn = output
+        .role
+        .and_then(|role| role.arn)
+        .ok_or_else(|| miette::miette!("missing role arn after creating `{}`", name))?;
+
+    iam_client
+        .attach_role_policy()
+        .role_name(name)
+        .policy_arn(policy_arn)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err(format!("failed to attach policy to role `{}`", name))?;
+
+    Ok(arn)
+}
@@ -0,0 +1,223 @@
+use crate::{alias, canary::CanaryCheck};
+use cargo_lambda_interactive::progress::Progress;
+use cargo_lambda_remote::aws_sdk_lambda::Client as LambdaClient;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{collections::HashMap, str::FromStr, time::Duration};
+
+#[derive(Clone, Debug)]
+pub(crate) enum DeploymentStrategy {
+    AllAtOnce,
+    Canary { pct: u8, interval: Duration },
+    Linear { step_pct: u8, interval: Duration },
+}
+
+impl FromStr for DeploymentStrategy {
+    type Err = miette::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("all-at-once") {
+            return Ok(DeploymentStrategy::AllAtOnce);
+        }
+
+        let (kind, rest) = s.split_once(':').ok_or_else(|| {
+            miette::miette!(
+                "invalid --deployment-strategy `{s}`, expected `all-at-once`, `canary:<pct>/<interval-secs>`, or `linear:<step-pct>/<interval-secs>`"
+            )
+        })?;
+        let (pct, interval_secs) = rest.split_once('/').ok_or_else(|| {
+            miette::miette!("invalid --deployment-strategy `{s}`, missing `/<interval-secs>`")
+        })?;
+        let pct: u8 = pct
+            .parse()
+            .into_diagnostic()
+            .wrap_err(format!("invalid percentage in --deployment-strategy `{s}`"))?;
+        if !(1..=100).contains(&pct) {
+            return Err(miette::miette!(
+                "invalid percentage `{pct}` in --deployment-strategy `{s}`, must be between 1 and 100"
+            ));
+        }
+        if kind == "linear" && pct == 100 {
+            return Err(miette::miette!(
+                "invalid step percentage `100` in --deployment-strategy `{s}`, a linear rollout must reach 100% gradually; use `all-at-once` to skip straight to 100%"
+            ));
+        }
+        let interval_secs: u64 = interval_secs
+            .parse()
+            .into_diagnostic()
+            .wrap_err(format!("invalid interval in --deployment-strategy `{s}`"))?;
+        let interval = Duration::from_secs(interval_secs);
+
+        match kind {
+            "canary" => Ok(DeploymentStrategy::Canary { pct, interval }),
+            "linear" => Ok(DeploymentStrategy::Linear {
+                step_pct: pct,
+                interval,
+            }),
+            other => Err(miette::miette!(
+                "invalid --deployment-strategy kind `{other}`, expected `all-at-once`, `canary`, or `linear`"
+            )),
+        }
+    }
+}
+
+/// Gradually shifts traffic on `alias_name` from whatever it currently
+/// points at over to `new_version`, following `strategy`. If `check` is
+/// present, it's invoked against `new_version` after each step's weight and
+/// interval, so before every further weight increase and before the final
+/// cutover to 100% (the first step is applied unchecked); any failure aborts the rollout,
+/// resetting the alias back to the version it had before this deploy.
+pub(crate) async fn roll_out(
+    lambda_client: &LambdaClient,
+    name: &str,
+    alias_name: &str,
+    new_version: &str,
+    strategy: &DeploymentStrategy,
+    check: Option<&CanaryCheck>,
+    progress: &Progress,
+) -> Result<()> {
+    if let DeploymentStrategy::AllAtOnce = strategy {
+        if let Some(check) = check {
+            crate::canary::invoke_canary(lambda_client, name, new_version, check, progress).await?;
+        }
+        alias::point_to(lambda_client, name, alias_name, new_version).await?;
+        return Ok(());
+    }
+
+    let previous_version = alias::get_version(lambda_client, name, alias_name).await?;
+    let Some(previous_version) = previous_version else {
+        // Nothing to shift away from yet, so there's nothing gradual to do.
+        if let Some(check) = check {
+            crate::canary::invoke_canary(lambda_client, name, new_version, check, progress).await?;
+        }
+        alias::point_to(lambda_client, name, alias_name, new_version).await?;
+        return Ok(());
+    };
+
+    let steps = match strategy {
+        DeploymentStrategy::AllAtOnce => unreachable!(),
+        DeploymentStrategy::Canary { pct, interval } => vec![(*pct, *interval)],
+        DeploymentStrategy::Linear { step_pct, interval } => {
+            let mut weight = *step_pct;
+            let mut steps = Vec::new();
+            while weight < 100 {
+                steps.push((weight, *interval));
+                weight = weight.saturating_add(*step_pct);
+            }
+            steps
+        }
+    };
+
+    let mut reached_full_cutover = false;
+
+    for (weight, interval) in steps {
+        progress.set_message(format!(
+            "shifting {weight}% of traffic on `{alias_name}` to version {new_version}"
+        ));
+
+        // Lambda rejects an additional-version weight of 1.0 (the primary
+        // `FunctionVersion` must retain some portion), so a 100%-weight step
+        // can't go through `set_routing`; it's handled as the cutover below
+        // instead, after the same interval wait and canary check as any
+        // other step.
+        if weight < 100 {
+            let mut weights = HashMap::new();
+            weights.insert(new_version.to_string(), weight as f64 / 100.0);
+            alias::set_routing(lambda_client, name, alias_name, &previous_version, weights).await?;
+        }
+
+        if !interval.is_zero() {
+            tokio::time::sleep(interval).await;
+        }
+
+        if let Some(check) = check {
+            if let Err(err) =
+                crate::canary::invoke_canary(lambda_client, name, new_version, check, progress).await
+            {
+                progress.set_message(format!("rollout failed, rolling back `{alias_name}`"));
+                alias::point_to(lambda_client, name, alias_name, &previous_version).await?;
+                return Err(err);
+            }
+        }
+
+        reached_full_cutover = weight == 100;
+    }
+
+    if !reached_full_cutover {
+        if let Some(check) = check {
+            progress.set_message(format!(
+                "checking version {new_version} before finalizing `{alias_name}` at 100%"
+            ));
+            if let Err(err) =
+                crate::canary::invoke_canary(lambda_client, name, new_version, check, progress).await
+            {
+                progress.set_message(format!("rollout failed, rolling back `{alias_name}`"));
+                alias::point_to(lambda_client, name, alias_name, &previous_version).await?;
+                return Err(err);
+            }
+        }
+    }
+
+    progress.set_message(format!("finalizing `{alias_name}` on version {new_version}"));
+    alias::point_to(lambda_client, name, alias_name, new_version).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_all_at_once() {
+        assert!(matches!(
+            "all-at-once".parse::<DeploymentStrategy>().unwrap(),
+            DeploymentStrategy::AllAtOnce
+        ));
+    }
+
+    #[test]
+    fn parses_canary() {
+        let strategy: DeploymentStrategy = "canary:10/60".parse().unwrap();
+        assert!(matches!(
+            strategy,
+            DeploymentStrategy::Canary { pct: 10, interval } if interval == Duration::from_secs(60)
+        ));
+    }
+
+    #[test]
+    fn parses_linear() {
+        let strategy: DeploymentStrategy = "linear:25/30".parse().unwrap();
+        assert!(matches!(
+            strategy,
+            DeploymentStrategy::Linear { step_pct: 25, interval } if interval == Duration::from_secs(30)
+        ));
+    }
+
+    #[test]
+    fn rejects_zero_step_percentage() {
+        assert!("linear:0/30".parse::<DeploymentStrategy>().is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_percentage() {
+        assert!("canary:101/30".parse::<DeploymentStrategy>().is_err());
+        assert!("linear:200/30".parse::<DeploymentStrategy>().is_err());
+    }
+
+    #[test]
+    fn rejects_full_linear_step_percentage() {
+        // step_pct == 100 would skip the gradual rollout entirely, with no
+        // intermediate weight and no interval wait before promoting to 100%.
+        assert!("linear:100/30".parse::<DeploymentStrategy>().is_err());
+    }
+
+    #[test]
+    fn allows_full_canary_percentage() {
+        // unlike linear, a single 100%-weight canary step still waits its
+        // interval and runs the health check before `roll_out` finalizes the alias.
+        assert!("canary:100/30".parse::<DeploymentStrategy>().is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_kind() {
+        assert!("blue-green:10/30".parse::<DeploymentStrategy>().is_err());
+    }
+}
@@ -0,0 +1,294 @@
+use cargo_lambda_interactive::progress::Progress;
+use cargo_lambda_remote::aws_sdk_s3::{types::CompletedMultipartUpload, types::CompletedPart, Client as S3Client};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::path::Path;
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt, SeekFrom},
+};
+
+/// Archives at or above this size are uploaded with a multipart request
+/// instead of a single `PutObject` call.
+const MULTIPART_THRESHOLD: u64 = 16 * 1024 * 1024;
+/// Size of each part in a multipart upload, except possibly the last one.
+const PART_SIZE: u64 = 8 * 1024 * 1024;
+/// How many parts are allowed to be in flight at the same time.
+const MAX_CONCURRENT_PARTS: usize = 4;
+/// How many attempts, the first included, a part gets before the whole upload is aborted.
+const MAX_PART_ATTEMPTS: usize = 3;
+
+/// Uploads `archive_path` to `bucket`/`key`, using a multipart upload for
+/// archives at or above [`MULTIPART_THRESHOLD`] and a single `PutObject`
+/// for everything else.
+pub(crate) async fn upload_archive(
+    s3_client: &S3Client,
+    bucket: &str,
+    key: &str,
+    archive_path: &Path,
+    progress: &Progress,
+) -> Result<()> {
+    let size = tokio::fs::metadata(archive_path)
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to read archive metadata")?
+        .len();
+
+    if size < MULTIPART_THRESHOLD {
+        progress.set_message("uploading code to S3");
+        let body = tokio::fs::read(archive_path)
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to read binary archive")?;
+
+        s3_client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .body(body.into())
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to upload the code to S3")?;
+
+        return Ok(());
+    }
+
+    upload_multipart(s3_client, bucket, key, archive_path, size, progress).await
+}
+
+async fn upload_multipart(
+    s3_client: &S3Client,
+    bucket: &str,
+    key: &str,
+    archive_path: &Path,
+    size: u64,
+    progress: &Progress,
+) -> Result<()> {
+    progress.set_message("starting multipart upload to S3");
+    let create = s3_client
+        .create_multipart_upload()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to create the multipart upload")?;
+
+    let upload_id = create
+        .upload_id
+        .ok_or_else(|| miette::miette!("S3 didn't return an upload id"))?;
+
+    match upload_parts(s3_client, bucket, key, &upload_id, archive_path, size, progress).await {
+        Ok(parts) => {
+            progress.set_message("completing multipart upload");
+            s3_client
+                .complete_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .multipart_upload(
+                    CompletedMultipartUpload::builder()
+                        .set_parts(Some(parts))
+                        .build(),
+                )
+                .send()
+                .await
+                .into_diagnostic()
+                .wrap_err("failed to complete the multipart upload")?;
+
+            Ok(())
+        }
+        Err(err) => {
+            // Don't leave orphaned parts behind if any of them failed.
+            if let Err(abort_err) = s3_client
+                .abort_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .send()
+                .await
+            {
+                tracing::warn!(
+                    %upload_id,
+                    "failed to abort multipart upload, orphaned parts may remain in `{bucket}/{key}`: {abort_err}"
+                );
+            }
+
+            Err(err)
+        }
+    }
+}
+
+async fn upload_parts(
+    s3_client: &S3Client,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    archive_path: &Path,
+    size: u64,
+    progress: &Progress,
+) -> Result<Vec<CompletedPart>> {
+    let plan = part_plan(size);
+    let part_count = plan.len() as u64;
+    let mut in_flight = Vec::new();
+    let mut parts = Vec::with_capacity(plan.len());
+
+    for (index, (part_number, offset, part_size)) in plan.into_iter().enumerate() {
+        let task = upload_one_part(
+            s3_client.clone(),
+            bucket.to_string(),
+            key.to_string(),
+            upload_id.to_string(),
+            archive_path.to_path_buf(),
+            offset,
+            part_size,
+            part_number,
+        );
+        in_flight.push(task);
+
+        if in_flight.len() >= MAX_CONCURRENT_PARTS || index as u64 + 1 == part_count {
+            progress.set_message(format!("uploading part {part_number}/{part_count}"));
+            for result in futures::future::join_all(in_flight.drain(..)).await {
+                parts.push(result?);
+            }
+        }
+    }
+
+    parts.sort_by_key(|part| part.part_number);
+    Ok(parts)
+}
+
+/// Splits an archive of `size` bytes into `(part_number, offset, part_size)`
+/// tuples of at most [`PART_SIZE`] bytes each, with the last part taking
+/// whatever remainder is left.
+fn part_plan(size: u64) -> Vec<(i32, u64, u64)> {
+    let part_count = size.div_ceil(PART_SIZE);
+    (1..=part_count)
+        .map(|part_number| {
+            let offset = (part_number - 1) * PART_SIZE;
+            let part_size = std::cmp::min(PART_SIZE, size - offset);
+            (part_number as i32, offset, part_size)
+        })
+        .collect()
+}
+
+async fn upload_one_part(
+    s3_client: S3Client,
+    bucket: String,
+    key: String,
+    upload_id: String,
+    archive_path: std::path::PathBuf,
+    offset: u64,
+    part_size: u64,
+    part_number: i32,
+) -> Result<CompletedPart> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match upload_part_once(
+            &s3_client,
+            &bucket,
+            &key,
+            &upload_id,
+            &archive_path,
+            offset,
+            part_size,
+            part_number,
+        )
+        .await
+        {
+            Ok(part) => return Ok(part),
+            Err(err) if attempt < MAX_PART_ATTEMPTS => {
+                tracing::warn!(part_number, attempt, "retrying failed part upload: {err}");
+                continue;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+async fn upload_part_once(
+    s3_client: &S3Client,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    archive_path: &Path,
+    offset: u64,
+    part_size: u64,
+    part_number: i32,
+) -> Result<CompletedPart> {
+    let mut file = File::open(archive_path)
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to open binary archive")?;
+    file.seek(SeekFrom::Start(offset))
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to seek into binary archive")?;
+
+    let mut buffer = vec![0u8; part_size as usize];
+    file.read_exact(&mut buffer)
+        .await
+        .into_diagnostic()
+        .wrap_err("failed to read part of the binary archive")?;
+
+    let output = s3_client
+        .upload_part()
+        .bucket(bucket)
+        .key(key)
+        .upload_id(upload_id)
+        .part_number(part_number)
+        .body(buffer.into())
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err(format!("failed to upload part {part_number}"))?;
+
+    let e_tag = output
+        .e_tag
+        .ok_or_else(|| miette::miette!("S3 didn't return an etag for part {part_number}"))?;
+
+    Ok(CompletedPart::builder()
+        .part_number(part_number)
+        .e_tag(e_tag)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_exact_multiple_of_part_size() {
+        let plan = part_plan(PART_SIZE * 3);
+        assert_eq!(
+            plan,
+            vec![
+                (1, 0, PART_SIZE),
+                (2, PART_SIZE, PART_SIZE),
+                (3, PART_SIZE * 2, PART_SIZE),
+            ]
+        );
+    }
+
+    #[test]
+    fn last_part_takes_the_remainder() {
+        let plan = part_plan(PART_SIZE * 2 + 100);
+        assert_eq!(
+            plan,
+            vec![(1, 0, PART_SIZE), (2, PART_SIZE, PART_SIZE), (3, PART_SIZE * 2, 100)]
+        );
+    }
+
+    #[test]
+    fn single_part_for_small_archive() {
+        let plan = part_plan(100);
+        assert_eq!(plan, vec![(1, 0, 100)]);
+    }
+
+    #[test]
+    fn zero_byte_archive_has_no_parts() {
+        let plan = part_plan(0);
+        assert!(plan.is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{extract_tags, roles};
+use cargo_lambda_interactive::progress::Progress;
+use cargo_lambda_remote::{
+    aws_sdk_iam::Client as IamClient,
+    aws_sdk_sfn::Client as SfnClient,
+};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::path::Path;
+
+#[derive(serde::Serialize)]
+pub struct DeployOutput {
+    pub name: String,
+    pub arn: String,
+}
+
+impl std::fmt::Display for DeployOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "🪄 state machine {} deployed ({})", self.name, self.arn)
+    }
+}
+
+/// Reads the Amazon States Language definition at `path`, resolves any
+/// `${cargo_lambda:<name>}` placeholder that refers to the function this
+/// invocation just deployed, and creates or updates a state machine with
+/// that definition.
+pub(crate) async fn deploy(
+    path: &Path,
+    deployed_name: &str,
+    deployed_arn: &str,
+    sdk_config: &aws_types::SdkConfig,
+    tags: &Option<Vec<String>>,
+    progress: &Progress,
+) -> Result<DeployOutput> {
+    progress.set_message("reading state machine definition");
+    let raw = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err(format!("failed to read state machine definition {path:?}"))?;
+
+    let resolved = resolve_placeholder(&raw, deployed_name, deployed_arn);
+
+    let unresolved = unresolved_placeholders(&resolved);
+    if !unresolved.is_empty() {
+        return Err(miette::miette!(
+            "state machine definition {path:?} references unresolved placeholder(s) {}, only `${{cargo_lambda:{deployed_name}}}` is resolved by this deploy",
+            unresolved.join(", ")
+        ));
+    }
+
+    let is_yaml = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("yaml") | Some("yml")
+    );
+    let definition = if is_yaml {
+        let value: serde_json::Value = serde_yaml::from_str(&resolved)
+            .into_diagnostic()
+            .wrap_err("failed to parse state machine definition as YAML")?;
+        serde_json::to_string(&value)
+            .into_diagnostic()
+            .wrap_err("failed to convert state machine definition to JSON")?
+    } else {
+        serde_json::from_str::<serde_json::Value>(&resolved)
+            .into_diagnostic()
+            .wrap_err("failed to parse state machine definition as JSON")?;
+        resolved
+    };
+
+    let name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| miette::miette!("invalid state machine definition path {path:?}"))?
+        .to_string();
+
+    let iam_client = IamClient::new(sdk_config);
+    let role_name = format!("cargo-lambda-state-machine-role-{name}");
+    let role_arn = roles::get_or_create_state_machine_role(&iam_client, &role_name).await?;
+
+    let sfn_client = SfnClient::new(sdk_config);
+    let arn = match sfn_client
+        .create_state_machine()
+        .name(&name)
+        .definition(&definition)
+        .role_arn(&role_arn)
+        .send()
+        .await
+    {
+        Ok(output) => output
+            .state_machine_arn
+            .ok_or_else(|| miette::miette!("missing state machine arn in the response"))?,
+        Err(err)
+            if err
+                .as_service_error()
+                .is_some_and(|e| e.is_state_machine_already_exists()) =>
+        {
+            update_state_machine(&sfn_client, &name, &definition, &role_arn).await?
+        }
+        Err(err) => {
+            return Err(err)
+                .into_diagnostic()
+                .wrap_err(format!("failed to create the state machine `{name}`"))
+        }
+    };
+
+    if let Some(tags) = tags {
+        let tags = extract_tags(tags);
+        let tag_list: Vec<_> = tags
+            .into_iter()
+            .map(|(key, value)| {
+                cargo_lambda_remote::aws_sdk_sfn::model::Tag::builder()
+                    .key(key)
+                    .value(value)
+                    .build()
+            })
+            .collect();
+
+        sfn_client
+            .tag_resource()
+            .resource_arn(&arn)
+            .set_tags(Some(tag_list))
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to tag the state machine")?;
+    }
+
+    Ok(DeployOutput { name, arn })
+}
+
+async fn update_state_machine(
+    sfn_client: &SfnClient,
+    name: &str,
+    definition: &str,
+    role_arn: &str,
+) -> Result<String> {
+    let existing = find_state_machine_arn(sfn_client, name).await?;
+
+    sfn_client
+        .update_state_machine()
+        .state_machine_arn(&existing)
+        .definition(definition)
+        .role_arn(role_arn)
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err(format!("failed to update the state machine `{name}`"))?;
+
+    Ok(existing)
+}
+
+async fn find_state_machine_arn(sfn_client: &SfnClient, name: &str) -> Result<String> {
+    let mut next_token = None;
+
+    loop {
+        let output = sfn_client
+            .list_state_machines()
+            .set_next_token(next_token.clone())
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to list existing state machines")?;
+
+        if let Some(arn) = output
+            .state_machines
+            .unwrap_or_default()
+            .into_iter()
+            .find(|sm| sm.name.as_deref() == Some(name))
+            .and_then(|sm| sm.state_machine_arn)
+        {
+            return Ok(arn);
+        }
+
+        next_token = output.next_token;
+        if next_token.is_none() {
+            return Err(miette::miette!(
+                "couldn't find the existing state machine `{name}`"
+            ));
+        }
+    }
+}
+
+/// Resolves a `${cargo_lambda:<name>}` placeholder referring to the function
+/// that was just deployed to its real ARN.
+fn resolve_placeholder(definition: &str, deployed_name: &str, deployed_arn: &str) -> String {
+    let placeholder = format!("${{cargo_lambda:{deployed_name}}}");
+    definition.replace(&placeholder, deployed_arn)
+}
+
+/// Finds every `${cargo_lambda:<name>}` placeholder still left in `definition`,
+/// e.g. because it refers to a function this invocation didn't deploy.
+fn unresolved_placeholders(definition: &str) -> Vec<&str> {
+    let mut placeholders = Vec::new();
+    let mut rest = definition;
+    while let Some(start) = rest.find("${cargo_lambda:") {
+        rest = &rest[start..];
+        match rest.find('}') {
+            Some(end) => {
+                placeholders.push(&rest[..=end]);
+                rest = &rest[end + 1..];
+            }
+            None => break,
+        }
+    }
+    placeholders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_matching_placeholder() {
+        let definition = r#"{"Resource": "${cargo_lambda:my-fn}"}"#;
+        let resolved = resolve_placeholder(definition, "my-fn", "arn:aws:lambda:us-east-1:1234:function:my-fn");
+        assert_eq!(
+            resolved,
+            r#"{"Resource": "arn:aws:lambda:us-east-1:1234:function:my-fn"}"#
+        );
+    }
+
+    #[test]
+    fn leaves_non_matching_placeholders_untouched() {
+        let definition = r#"{"Resource": "${cargo_lambda:other-fn}"}"#;
+        let resolved = resolve_placeholder(definition, "my-fn", "arn:aws:lambda:us-east-1:1234:function:my-fn");
+        assert_eq!(resolved, definition);
+    }
+
+    #[test]
+    fn resolves_repeated_placeholders() {
+        let definition = r#"["${cargo_lambda:my-fn}", "${cargo_lambda:my-fn}"]"#;
+        let resolved = resolve_placeholder(definition, "my-fn", "arn:my-fn");
+        assert_eq!(resolved, r#"["arn:my-fn", "arn:my-fn"]"#);
+    }
+
+    #[test]
+    fn finds_no_unresolved_placeholders_once_resolved() {
+        let definition = r#"{"Resource": "arn:aws:lambda:us-east-1:1234:function:my-fn"}"#;
+        assert!(unresolved_placeholders(definition).is_empty());
+    }
+
+    #[test]
+    fn finds_unresolved_placeholder_referencing_another_function() {
+        let definition = r#"{"Resource": "${cargo_lambda:other-fn}"}"#;
+        assert_eq!(
+            unresolved_placeholders(definition),
+            vec!["${cargo_lambda:other-fn}"]
+        );
+    }
+
+    #[test]
+    fn finds_every_unresolved_placeholder_occurrence() {
+        let definition = r#"["${cargo_lambda:a}", "${cargo_lambda:b}", "${cargo_lambda:a}"]"#;
+        assert_eq!(
+            unresolved_placeholders(definition),
+            vec!["${cargo_lambda:a}", "${cargo_lambda:b}", "${cargo_lambda:a}"]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use aws_config::sts::AssumeRoleProvider;
+use aws_types::SdkConfig;
+
+const DEFAULT_SESSION_NAME: &str = "cargo-lambda";
+
+/// Re-configures `base_config` so that its credentials come from assuming
+/// each role in `role_arns` (comma separated) in order, feeding the
+/// credentials obtained from one hop into the next. This supports a simple
+/// delegation chain: the first role is assumed with the base credentials,
+/// the second role is assumed with the first role's credentials, and so on.
+pub(crate) async fn chain(
+    base_config: &SdkConfig,
+    role_arns: &str,
+    session_name: &Option<String>,
+    external_id: &Option<String>,
+) -> SdkConfig {
+    let session_name = session_name
+        .clone()
+        .unwrap_or_else(|| DEFAULT_SESSION_NAME.to_string());
+
+    let mut config = base_config.clone();
+    for role_arn in parse_role_chain(role_arns) {
+        let mut builder = AssumeRoleProvider::builder(role_arn)
+            .session_name(session_name.clone())
+            .configure(&config);
+        if let Some(external_id) = external_id {
+            builder = builder.external_id(external_id.clone());
+        }
+
+        // `AssumeRoleProvider` caches and refreshes its own credentials as
+        // they near expiry, so long multipart uploads keep working across a
+        // single invocation without any extra bookkeeping here.
+        let provider = builder.build().await;
+        config = config.to_builder().credentials_provider(provider).build();
+    }
+
+    config
+}
+
+/// Splits a comma separated chain of role ARNs into the individual ARNs,
+/// trimming whitespace and dropping empty entries left by stray commas.
+fn parse_role_chain(role_arns: &str) -> Vec<&str> {
+    role_arns
+        .split(',')
+        .map(str::trim)
+        .filter(|arn| !arn.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_single_role() {
+        assert_eq!(parse_role_chain("arn:aws:iam::111:role/a"), vec!["arn:aws:iam::111:role/a"]);
+    }
+
+    #[test]
+    fn parses_a_chain_of_roles_in_order() {
+        assert_eq!(
+            parse_role_chain("arn:aws:iam::111:role/a,arn:aws:iam::222:role/b"),
+            vec!["arn:aws:iam::111:role/a", "arn:aws:iam::222:role/b"]
+        );
+    }
+
+    #[test]
+    fn trims_whitespace_around_each_role() {
+        assert_eq!(
+            parse_role_chain(" arn:aws:iam::111:role/a , arn:aws:iam::222:role/b "),
+            vec!["arn:aws:iam::111:role/a", "arn:aws:iam::222:role/b"]
+        );
+    }
+
+    #[test]
+    fn drops_empty_entries_from_stray_commas() {
+        assert_eq!(
+            parse_role_chain("arn:aws:iam::111:role/a,,arn:aws:iam::222:role/b,"),
+            vec!["arn:aws:iam::111:role/a", "arn:aws:iam::222:role/b"]
+        );
+    }
+
+    #[test]
+    fn parses_an_empty_chain_as_no_roles() {
+        assert!(parse_role_chain("").is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+pub use aws_sdk_iam;
+pub use aws_sdk_lambda;
+pub use aws_sdk_s3;
+pub use aws_sdk_sfn;
+
+use aws_config::{meta::region::RegionProviderChain, BehaviorVersion};
+use aws_sdk_lambda::model::Runtime;
+use aws_smithy_types::retry::RetryConfig;
+use aws_types::{region::Region, SdkConfig};
+use clap::Args;
+
+mod assume_role;
+
+/// Configuration shared by every subcommand that talks to AWS.
+#[derive(Args, Clone, Debug)]
+pub struct RemoteConfig {
+    /// AWS region to deploy to
+    #[arg(long)]
+    pub region: Option<String>,
+
+    /// AWS configuration profile to use
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Lambda runtime to use for the function
+    #[arg(long, default_value = "provided.al2")]
+    pub runtime: String,
+
+    /// Comma separated chain of IAM role ARNs to assume before talking to AWS (role1,role2
+    /// assumes role1 with the default credentials first, then role2 with role1's credentials).
+    /// Lets this command operate against another account without pre-baked credentials.
+    #[arg(long)]
+    pub assume_role: Option<String>,
+
+    /// Session name to use for the STS AssumeRole calls
+    #[arg(long, requires = "assume_role")]
+    pub role_session_name: Option<String>,
+
+    /// External ID to pass to the STS AssumeRole calls
+    #[arg(long, requires = "assume_role")]
+    pub external_id: Option<String>,
+}
+
+impl RemoteConfig {
+    /// Builds the SDK configuration used to talk to AWS, honoring `--region`/`--profile` and,
+    /// if set, assuming the `--assume-role` chain before returning.
+    pub async fn sdk_config(&self, retry: Option<RetryConfig>) -> SdkConfig {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+
+        if let Some(region) = &self.region {
+            let region = RegionProviderChain::first_try(Region::new(region.clone()));
+            loader = loader.region(region);
+        }
+        if let Some(profile) = &self.profile {
+            loader = loader.profile_name(profile);
+        }
+        if let Some(retry) = retry {
+            loader = loader.retry_config(retry);
+        }
+
+        let config = loader.load().await;
+
+        match &self.assume_role {
+            Some(role_arns) => {
+                assume_role::chain(&config, role_arns, &self.role_session_name, &self.external_id)
+                    .await
+            }
+            None => config,
+        }
+    }
+
+    pub fn runtime(&self) -> Runtime {
+        Runtime::from(self.runtime.as_str())
+    }
+}